@@ -0,0 +1,2074 @@
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use indicatif::HumanBytes;
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env,
+    ffi::{OsStr, OsString},
+    fs,
+    io::{self, BufRead, BufReader, ErrorKind, IsTerminal, Write},
+    path::{Component, Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const ROOT_DIR_ERROR: &str =
+    "The current directory is the filesystem root, so no program name can be derived from it; pass --program-name";
+
+const ERROR_ADVICE: &[(&str, &str)] = &[
+    (
+        "Command not found: cmake",
+        "CMake is not installed or not on PATH. Install CMake, or pass --tool-root cmake=<dir>",
+    ),
+    (
+        "not found in PATH",
+        "The compiler is missing. Install GCC (MinGW-w64 on Windows) or Clang, pick the other one with --toolchain, \
+         or put an existing installation on PATH with --tool-root",
+    ),
+    (
+        "No CMakeLists.txt found",
+        "The project directory has no CMakeLists.txt. Check --project, or run `init` to scaffold a project",
+    ),
+    (
+        "contains spaces",
+        "Move the project to a path without spaces, MinGW Makefiles cannot quote them",
+    ),
+    (
+        "contains non-ASCII characters",
+        "Move the project to a plain ASCII path, MinGW tools often cannot open such paths",
+    ),
+    (
+        "timed out",
+        "The command hung or is slower than the limit. Raise --build-timeout or --run-timeout, or drop them",
+    ),
+    (
+        "Program exited with status",
+        "The build succeeded but the program itself failed, see its output above. Run it under gdb with \
+         --debug-run to investigate",
+    ),
+    (
+        "Command execution failed",
+        "A build tool reported an error, see its output above. If the CMake cache is stale (e.g. it was \
+         created with another generator or compiler), retry with --reconfigure",
+    ),
+];
+
+static SUMMARY_ONLY: AtomicBool = AtomicBool::new(false);
+
+// PATH extended by --tool-root; handed to each child instead of modifying the process environment
+static TOOL_PATH: Mutex<Option<OsString>> = Mutex::new(None);
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if !summary_only() {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "CMake Build System in Rust", long_about = None)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+    #[arg(short, long, default_value = "vscode")]
+    pub config_ide: String,
+    #[arg(short, long)]
+    pub architecture: Option<String>,
+    #[arg(short, long, default_value = "Debug")]
+    pub build_type: String,
+    #[arg(short, long)]
+    pub program_name: Option<String>,
+    #[arg(long)]
+    pub cmake_args_file: Option<String>,
+    #[arg(long)]
+    pub no_title: bool,
+    #[arg(long)]
+    pub debug_run: bool,
+    #[arg(long)]
+    pub list_targets: bool,
+    #[arg(short, long, env = "CMAKE_GENERATOR")]
+    pub generator: Option<String>,
+    #[arg(short, long, env = "CMAKE_BUILD_PARALLEL_LEVEL")]
+    pub jobs: Option<String>,
+    #[arg(long)]
+    pub static_runtime: bool,
+    #[arg(long)]
+    pub open_ide: bool,
+    #[arg(long, value_name = "ALIAS=TYPE")]
+    pub build_type_alias: Vec<String>,
+    #[arg(long, value_name = "SECS")]
+    pub build_timeout: Option<u64>,
+    #[arg(long)]
+    pub target_triple: Option<String>,
+    #[arg(long)]
+    pub show_config: bool,
+    #[arg(long, value_name = "SCRIPT")]
+    pub pre_configure_script: Vec<String>,
+    #[arg(long)]
+    pub open_build_dir: bool,
+    #[arg(long, default_value = "gcc")]
+    pub toolchain: String,
+    #[arg(long)]
+    pub strict: bool,
+    #[arg(long)]
+    pub build_only_if_changed: bool,
+    #[arg(long, value_name = "NAME=PATH")]
+    pub tool_root: Vec<String>,
+    #[arg(long)]
+    pub strip_symbols: bool,
+    #[arg(long, value_name = "FILE")]
+    pub cmake_cache_init: Option<String>,
+    #[arg(long, value_name = "GENERATOR", num_args = 0..=1, default_missing_value = "")]
+    pub package: Option<String>,
+    #[arg(long, value_name = "SECS")]
+    pub run_timeout: Option<u64>,
+    #[arg(long)]
+    pub summary_only: bool,
+    #[arg(long, value_name = "SUBDIR")]
+    pub project: Option<String>,
+    #[arg(long)]
+    pub reconfigure: bool,
+    #[arg(long, value_name = "VERSION")]
+    pub require_compiler_version: Option<String>,
+    #[arg(long)]
+    pub no_version_check: bool,
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub env: Vec<String>,
+    #[arg(long, value_name = "PATH")]
+    pub env_file: Option<String>,
+    #[arg(long, value_name = "PATH")]
+    pub tee_output: Option<String>,
+    #[arg(long, value_name = "SUFFIX")]
+    pub build_dir_suffix: Option<String>,
+    #[arg(long, value_name = "MS")]
+    pub before_run_delay: Option<u64>,
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub sanitize: Vec<String>,
+    #[arg(long, conflicts_with = "list_targets")]
+    pub relink_only: bool,
+    #[arg(long, value_name = "DIR")]
+    pub include_dir: Vec<String>,
+    #[arg(long, value_name = "DIR")]
+    pub link_dir: Vec<String>,
+    #[arg(long)]
+    pub trace_cmake: bool,
+    #[arg(long, value_name = "PATH")]
+    pub stdin: Option<String>,
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub measure: Option<u32>,
+    #[arg(long)]
+    pub explain: bool,
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["project", "build_only_if_changed"])]
+    pub single: Option<String>,
+    #[arg(long)]
+    pub no_default_flags: bool,
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<String>,
+    #[arg(long, alias = "warn-as-error")]
+    pub warnings_as_errors: bool,
+    #[arg(long)]
+    pub clean_build_on_failure: bool,
+    #[arg(long)]
+    pub abort_on_warning: bool,
+    #[arg(long, value_name = "DIR")]
+    pub export_path_script: Option<String>,
+    #[arg(long)]
+    pub verbose_cmake: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "measure", "relink_only", "list_targets", "build_only_if_changed", "strip_symbols",
+            "build_timeout", "run_timeout", "tee_output", "before_run_delay", "clean_build_on_failure",
+        ]
+    )]
+    pub dump_commands: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    Completions {
+        shell: Shell,
+    },
+    Init,
+}
+
+lazy_static! {
+    static ref CURRENT_DIR_NAME: Option<OsString> = {
+        let current_dir = env::current_dir()
+            .expect("Failed to get current directory");
+
+        current_dir.file_name().map(OsStr::to_os_string)
+    };
+}
+
+pub fn explain_error(message: &str) -> Option<&'static str> {
+    ERROR_ADVICE
+        .iter()
+        .find(|(signature, _)| message.contains(signature))
+        .map(|(_, advice)| *advice)
+}
+
+pub fn set_summary_only(enabled: bool) {
+    SUMMARY_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+pub fn summary_only() -> bool {
+    SUMMARY_ONLY.load(Ordering::Relaxed)
+}
+
+fn summary(phase: &str, started: Instant) {
+    if summary_only() {
+        println!("✔ {} ({:.1}s)", phase, started.elapsed().as_secs_f64());
+    }
+}
+
+pub trait Progress: Sync {
+    fn phase(&self, _phase: &str) {}
+    fn percent(&self, _percent: u32) {}
+    fn finish(&self) {}
+}
+
+#[derive(Default)]
+pub struct TitleProgress {
+    phase: std::sync::Mutex<String>,
+}
+
+impl Progress for TitleProgress {
+    fn phase(&self, phase: &str) {
+        if let Ok(mut current) = self.phase.lock() {
+            *current = phase.to_string();
+        }
+    }
+
+    fn percent(&self, percent: u32) {
+        let phase = self.phase.lock().map(|phase| phase.clone()).unwrap_or_default();
+        set_title_progress(&format!("{}% - {}", percent, phase), percent);
+    }
+
+    fn finish(&self) {
+        clear_title_progress();
+    }
+}
+
+struct Steps {
+    current: usize,
+    total: usize,
+}
+
+impl Steps {
+    fn start(&mut self, phase: &str) {
+        self.current += 1;
+        info!("[{}/{}] {}...", self.current, self.total, phase);
+    }
+}
+
+fn report_phase(progress: Option<&dyn Progress>, phase: &str) {
+    if let Some(progress) = progress {
+        progress.phase(phase);
+    }
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let title = TitleProgress::default();
+    // The escape sequences would end up as garbage in redirected output or on a console without VT support
+    let use_title = std::env::consts::OS == "windows"
+        && !args.no_title
+        && !args.summary_only
+        && io::stdout().is_terminal()
+        && enable_virtual_terminal();
+    run_with_progress(args, use_title.then_some(&title as &dyn Progress))
+}
+
+pub fn run_with_progress(args: Args, progress: Option<&dyn Progress>) -> Result<()> {
+    set_summary_only(args.summary_only);
+    set_tool_path(None);
+
+    match args.command {
+        Some(Commands::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Args::command(), env!("CARGO_PKG_NAME"), &mut io::stdout());
+            return Ok(());
+        }
+        Some(Commands::Init) => return init_project(get_current_dir_name()?),
+        None => {}
+    }
+
+    let arch = match (args.architecture, &args.target_triple) {
+        (Some(arch), _) => arch,
+        // The triple selects the compilers, so the architecture only picks the default generator
+        (None, Some(_)) => host_architecture().unwrap_or("x64").to_string(),
+        (None, None) => host_architecture()?.to_string(),
+    };
+    let build_type = resolve_build_type(&args.build_type, &args.build_type_alias)?;
+    // A dumped script configures from the generated project, so it has to outlive this run
+    let keep_single_project = args.dump_commands.is_some();
+    let single_project = args
+        .single
+        .as_deref()
+        .map(|file| SingleFileProject::create(file, keep_single_project))
+        .transpose()?;
+    let source_dir = match &single_project {
+        Some(project) => utf8_path(&project.dir)?.to_string(),
+        None => match &args.project {
+            Some(project) => project_subdir(project)?,
+            None => ".".to_string(),
+        },
+    };
+    if args.project.is_some() && !Path::new(&source_dir).join("CMakeLists.txt").is_file() {
+        return Err(format!("No CMakeLists.txt found in project {}", source_dir).into());
+    }
+
+    let program_name = match args.program_name {
+        Some(name) if name.trim().is_empty() => return Err("--program-name must not be empty".into()),
+        Some(name) => name,
+        None => match &single_project {
+            Some(project) => project.name.clone(),
+            None => match Path::new(&source_dir).file_name() {
+                Some(name) => utf8_name(name)?.to_string(),
+                None => get_current_dir_name()?.to_string(),
+            },
+        },
+    };
+    let build_root = match (&single_project, &args.project) {
+        (Some(project), _) => format!("build/single/{}", project.name),
+        (None, Some(_)) => format!("build/{}", source_dir),
+        (None, None) => "build".to_string(),
+    };
+    let mut build_dir = format!("{}/{}-{}", build_root, build_type, args.target_triple.as_deref().unwrap_or(&arch));
+    if let Some(suffix) = &args.build_dir_suffix {
+        build_dir = format!("{}-{}", build_dir, suffix);
+    }
+    let bin_dir = match &args.output_dir {
+        Some(dir) => env::current_dir()?.join(dir),
+        // Without the injected output paths, single-config generators leave executables in the build dir
+        None if args.no_default_flags => env::current_dir()?.join(&build_dir),
+        None => env::current_dir()?.join(&build_dir).join("bin"),
+    };
+    let bin_dir = utf8_path(&bin_dir)?.to_string();
+
+    info!("{}", bin_dir);
+
+    let mut program_env = match &args.env_file {
+        Some(path) => read_env_file(path)?,
+        None => Vec::new(),
+    };
+    for var in &args.env {
+        let (key, value) = var
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid --env '{}', expected KEY=VALUE", var))?;
+        program_env.push((key.to_string(), value.to_string()));
+    }
+    if let Some(path) = &args.stdin
+        && !Path::new(path).is_file()
+    {
+        return Err(format!("Stdin file not found: {}", path).into());
+    }
+
+    for tool_root in &args.tool_root {
+        let (name, root) = tool_root
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid tool root '{}', expected NAME=PATH", tool_root))?;
+        add_tool_to_path(name, Path::new(root))?;
+    }
+
+    if let Some(dir) = &args.export_path_script {
+        let mut bin_dirs = Vec::new();
+        for tool_root in &args.tool_root {
+            let (_, root) = tool_root.split_once('=').unwrap_or_default();
+            bin_dirs.push(env::current_dir()?.join(root).join("bin"));
+        }
+        if bin_dirs.is_empty() {
+            return Err("--export-path-script needs at least one --tool-root to put on PATH".into());
+        }
+        return write_activation_scripts(Path::new(dir), &bin_dirs);
+    }
+
+    // CMake treats empty CMAKE_BUILD_PARALLEL_LEVEL / CMAKE_GENERATOR as unset, and so do we
+    let jobs = args.jobs.as_deref().filter(|jobs| !jobs.is_empty()).map(resolve_jobs).transpose()?;
+
+    let (default_generator, ..) = resolve_toolchain(&arch)?;
+    let generator = args.generator.as_deref().filter(|generator| !generator.is_empty()).unwrap_or(default_generator);
+
+    let (toolchain, mut flags, c_compiler, cxx_compiler) =
+        select_toolchain(&args.toolchain, &arch, args.target_triple.as_deref(), args.strict)?;
+
+    if args.static_runtime {
+        if c_compiler.contains("gcc") {
+            flags.push_str(" -static -static-libgcc -static-libstdc++");
+        } else {
+            eprintln!("Warning: --static-runtime is only supported with GCC, ignoring it");
+        }
+    }
+
+    if args.warnings_as_errors {
+        flags.push_str(" -Werror");
+    }
+
+    let sanitize_flag = (!args.sanitize.is_empty()).then(|| format!("-fsanitize={}", args.sanitize.join(",")));
+    if let Some(sanitize_flag) = &sanitize_flag {
+        if c_compiler.contains("mingw") {
+            eprintln!(
+                "Warning: MinGW GCC ships no sanitizer runtimes, {} will most likely fail to link",
+                sanitize_flag
+            );
+        }
+        flags.push(' ');
+        flags.push_str(sanitize_flag);
+    }
+
+    if args.show_config {
+        let settings = [
+            ("IDE", args.config_ide.clone()),
+            ("Architecture", arch.clone()),
+            ("Target triple", args.target_triple.clone().unwrap_or_else(|| "-".into())),
+            ("Build type", build_type.clone()),
+            ("Generator", generator.to_string()),
+            ("Toolchain", toolchain.to_string()),
+            ("Jobs", jobs.map_or_else(|| "default".into(), |jobs| jobs.to_string())),
+            ("C compiler", c_compiler.clone()),
+            ("CXX compiler", cxx_compiler.clone()),
+            ("Compiler flags", flags.clone()),
+            ("Source dir", source_dir.clone()),
+            ("Program name", program_name.clone()),
+            ("Build dir", build_dir.clone()),
+            ("Bin dir", bin_dir.clone()),
+            ("CMake args file", args.cmake_args_file.clone().unwrap_or_else(|| "-".into())),
+        ];
+        print_settings(&settings);
+        return Ok(());
+    }
+
+    check_project_path(&env::current_dir()?.join(&build_dir), args.strict)?;
+    ensure_writable(&build_dir)?;
+    check_compiler_target(&c_compiler, &flags, &arch, args.target_triple.as_deref());
+    if let Some(required) = &args.require_compiler_version
+        && !args.no_version_check
+    {
+        check_compiler_version(&cxx_compiler, required)?;
+    }
+
+    let mut config_params: Vec<String> = vec![
+        "-B".into(), build_dir.clone(),
+        "-S".into(), source_dir.clone(),
+        "-G".into(), generator.into(),
+        format!("-DCMAKE_BUILD_TYPE={}", build_type),
+        format!("-DCMAKE_C_FLAGS={}", flags),
+        format!("-DCMAKE_CXX_FLAGS={}", flags),
+        format!("-DCMAKE_C_COMPILER={}", c_compiler),
+        format!("-DCMAKE_CXX_COMPILER={}", cxx_compiler),
+    ];
+
+    if !args.no_default_flags {
+        config_params.push("-DCMAKE_EXPORT_COMPILE_COMMANDS=ON".into());
+    }
+    // An explicit --output-dir is the user's choice rather than a default, so it is passed on regardless
+    if !args.no_default_flags || args.output_dir.is_some() {
+        config_params.extend([
+            format!("-DEXECUTABLE_OUTPUT_PATH={}", bin_dir),
+            format!("-DLIBRARY_OUTPUT_PATH={}", bin_dir),
+        ]);
+    }
+
+    if let Some(sanitize_flag) = &sanitize_flag {
+        config_params.extend([
+            format!("-DCMAKE_EXE_LINKER_FLAGS={}", sanitize_flag),
+            format!("-DCMAKE_SHARED_LINKER_FLAGS={}", sanitize_flag),
+        ]);
+    }
+
+    if args.abort_on_warning {
+        config_params.extend(["--warn-uninitialized".to_string(), "-Werror=dev".to_string()]);
+    }
+
+    for (variable, dirs) in [("CMAKE_INCLUDE_PATH", &args.include_dir), ("CMAKE_LIBRARY_PATH", &args.link_dir)] {
+        if !dirs.is_empty() {
+            config_params.push(format!("-D{}={}", variable, search_paths(dirs)?));
+        }
+    }
+
+    if let Some(path) = &args.cmake_cache_init {
+        let cache_init = env::current_dir()?.join(path);
+        if !cache_init.is_file() {
+            return Err(format!("CMake cache init file not found: {}", path).into());
+        }
+        config_params.splice(0..0, ["-C".to_string(), cache_init.to_string_lossy().into_owned()]);
+    }
+
+    if let Some(path) = &args.cmake_args_file {
+        let extra_args = read_cmake_args_file(path)?;
+        if args.static_runtime && extra_args.iter().any(|arg| overrides_compiler_flags(arg)) {
+            eprintln!("Warning: {} sets CMAKE_C_FLAGS/CMAKE_CXX_FLAGS, which replaces the --static-runtime flags", path);
+        }
+        config_params.extend(extra_args);
+    }
+
+    let mut build_params = vec![
+        "--build".to_string(),
+        build_dir.clone(),
+    ];
+
+    if let Some(jobs) = jobs {
+        build_params.extend(["--parallel".to_string(), jobs.to_string()]);
+    }
+    if args.verbose_cmake {
+        build_params.push("--verbose".to_string());
+    }
+
+    if let Some(runs) = args.measure {
+        return measure_builds(runs, &build_dir, &config_params, &build_params);
+    }
+
+    if let Some(path) = &args.dump_commands {
+        let fresh = args.reconfigure && cmake_version().is_some_and(|version| version >= (3, 24));
+        if args.reconfigure && !fresh {
+            return Err("--dump-commands expresses --reconfigure as cmake --fresh, which needs CMake 3.24 or newer".into());
+        }
+
+        let (shell, flag) = system_shell();
+        let vars = pre_configure_env(&source_dir, &build_dir)?;
+        let mut commands: Vec<ScriptCommand> = args
+            .pre_configure_script
+            .iter()
+            .map(|script| ScriptCommand {
+                args: vec![shell.to_string(), flag.to_string(), script.clone()],
+                envs: vars.clone(),
+                stdin: None,
+            })
+            .collect();
+        commands.push(ScriptCommand::new("cmake", configure_command(&config_params, fresh, args.trace_cmake)));
+        commands.push(ScriptCommand::new("cmake", build_params.clone()));
+        if let Some(package_generator) = &args.package {
+            commands.push(ScriptCommand::new("cpack", cpack_params(&build_dir, package_generator)));
+        }
+        let exe_path = format!("{}/{}", bin_dir, program_name);
+        commands.push(if args.debug_run {
+            ScriptCommand { args: vec!["gdb".to_string(), exe_path], envs: program_env.clone(), stdin: None }
+        } else {
+            ScriptCommand { args: vec![exe_path], envs: program_env.clone(), stdin: args.stdin.clone() }
+        });
+        return dump_commands(Path::new(path), &commands);
+    }
+
+    let snapshot_path = format!("{}/source_snapshot.txt", build_dir);
+    let snapshot = if args.build_only_if_changed && !args.relink_only {
+        let output_dirs = [env::current_dir()?.join(&build_dir), PathBuf::from(&bin_dir)];
+        match source_snapshot(Path::new(&source_dir), &config_params, &output_dirs) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                eprintln!("Warning: Could not snapshot the sources ({}), building unconditionally", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // Listing targets needs the configure step, so it never takes the unchanged shortcut
+    let unchanged = !args.list_targets
+        && snapshot
+            .as_ref()
+            .is_some_and(|snapshot| fs::read_to_string(&snapshot_path).is_ok_and(|previous| previous == *snapshot));
+
+    let configuring = !args.relink_only && !unchanged;
+    // Link, pre-configure, configure, build, package and run, in pipeline order
+    let phases = [
+        args.relink_only,
+        configuring && !args.pre_configure_script.is_empty(),
+        configuring,
+        configuring && !args.list_targets,
+        args.package.is_some() && !args.list_targets,
+        !args.list_targets,
+    ];
+    let mut steps = Steps {
+        current: 0,
+        total: phases.iter().filter(|&&enabled| enabled).count(),
+    };
+
+    if args.relink_only {
+        if !Path::new(&build_dir).join("CMakeCache.txt").is_file() {
+            return Err(format!(
+                "{} has not been configured yet, run a full build before using --relink-only",
+                build_dir
+            )
+            .into());
+        }
+
+        let started = Instant::now();
+        steps.start("Linking");
+        report_phase(progress, &format!("Linking {}", program_name));
+        relink(&build_dir, &bin_dir, &program_name, generator)?;
+        summary("Link", started);
+    } else if unchanged {
+        info!("✅ Sources unchanged since the last build, skipping configure and build");
+        if summary_only() {
+            println!("✔ Build skipped, sources unchanged");
+        }
+    } else {
+        if !args.pre_configure_script.is_empty() {
+            let vars = pre_configure_env(&source_dir, &build_dir)?;
+            let started = Instant::now();
+            steps.start("Pre-configure");
+            report_phase(progress, "Pre-configure");
+            for script in &args.pre_configure_script {
+                run_shell_command(script, &vars)
+                    .map_err(|e| format!("Pre-configure script failed: {}", e))?;
+            }
+            summary("Pre-configure", started);
+        }
+
+        let started = Instant::now();
+        steps.start("Configuring");
+        report_phase(progress, "Configure");
+        clear_cache_on_generator_change(&build_dir, generator)?;
+        let cache = Path::new(&build_dir).join("CMakeCache.txt");
+        let fresh = args.reconfigure && cmake_version().is_some_and(|version| version >= (3, 24));
+        if args.reconfigure && !fresh && cache.exists() {
+            fs::remove_file(&cache)?;
+        }
+
+        let args_record = Path::new(&build_dir).join("configure_args.txt");
+        let configure_args = config_params.join("\n");
+        if !args.reconfigure
+            && !args.trace_cmake
+            && cache.is_file()
+            && fs::read_to_string(&args_record).is_ok_and(|previous| previous == configure_args)
+        {
+            info!("⏭️ Configuration unchanged, skipping configure");
+            if summary_only() {
+                println!("✔ Configure skipped, configuration unchanged");
+            }
+        } else {
+            // A configure that fails half way still rewrites the cache, so the old record must not survive it
+            if args_record.exists() {
+                fs::remove_file(&args_record)?;
+            }
+            run_command("cmake", &configure_command(&config_params, fresh, args.trace_cmake))
+                .map_err(|e| clean_build_on_failure(&build_dir, args.clean_build_on_failure, e))?;
+            fs::write(&args_record, &configure_args)?;
+            summary("Configure", started);
+        }
+
+        if args.open_ide {
+            open_ide(&args.config_ide)?;
+        }
+
+        if args.list_targets {
+            run_command("cmake", &["--build", &build_dir, "--target", "help"])?;
+            return Ok(());
+        }
+
+        let started = Instant::now();
+        steps.start("Building");
+        report_phase(progress, &format!("Building {}", program_name));
+        let build_timeout = args.build_timeout.map(Duration::from_secs);
+        let built = match progress {
+            // Ninja only draws its status line and keeps colours on a terminal, so its output is left alone
+            Some(progress) if !generator.contains("Ninja") => run_command_with_progress("cmake", &build_params, progress, build_timeout),
+            _ => run_command_with_timeout("cmake", &build_params, build_timeout),
+        };
+        built.map_err(|e| clean_build_on_failure(&build_dir, args.clean_build_on_failure, e))?;
+        summary("Build", started);
+
+        match &snapshot {
+            Some(snapshot) => fs::write(&snapshot_path, snapshot)?,
+            // A snapshot from an earlier run no longer describes what was just built
+            None if Path::new(&snapshot_path).exists() => fs::remove_file(&snapshot_path)?,
+            None => {}
+        }
+
+        if args.strip_symbols {
+            if matches!(build_type.as_str(), "Release" | "MinSizeRel") {
+                strip_executables(&bin_dir, &strip_command(&c_compiler))?;
+            } else {
+                info!("⏭️ Skipping --strip-symbols for {} build", build_type);
+            }
+        }
+
+        write_checksums(&bin_dir, &build_dir)?;
+        report_artifact_sizes(&bin_dir, &build_dir)?;
+    }
+
+    if let Some(package_generator) = &args.package {
+        let started = Instant::now();
+        steps.start("Packaging");
+        report_phase(progress, "Package");
+        package(&build_dir, package_generator)?;
+        summary("Package", started);
+    }
+
+    if args.open_build_dir {
+        open_in_file_manager(&bin_dir);
+    }
+
+    let started = Instant::now();
+    steps.start("Running");
+    report_phase(progress, "Run");
+    let exe_path = format!("{}/{}", bin_dir, program_name);
+    let before_run_delay = args.before_run_delay.map(Duration::from_millis);
+    if let Some(delay) = before_run_delay {
+        thread::sleep(delay);
+    }
+    let stdin = args.stdin.as_deref().map(Path::new);
+    if args.debug_run {
+        if stdin.is_some() {
+            eprintln!("Warning: --stdin is not passed to the program under --debug-run, ignoring it");
+        }
+        run_program("gdb", &[&exe_path], &program_env, None, None, None, None)?;
+    } else {
+        let tee_output = args.tee_output.as_deref().map(Path::new);
+        let run_timeout = args.run_timeout.map(Duration::from_secs);
+        run_program::<&str>(&exe_path, &[], &program_env, run_timeout, tee_output, stdin, before_run_delay)?;
+    }
+    summary("Run", started);
+
+    if summary_only() {
+        println!("✅ Success");
+    }
+
+    Ok(())
+}
+
+pub fn measure_builds(runs: u32, build_dir: &str, config_params: &[String], build_params: &[String]) -> Result<()> {
+    let mut durations = Vec::new();
+    for run in 1..=runs {
+        if Path::new(build_dir).exists() {
+            fs::remove_dir_all(build_dir)?;
+        }
+
+        let started = Instant::now();
+        run_command("cmake", config_params)?;
+        run_command("cmake", build_params)?;
+        let elapsed = started.elapsed().as_secs_f64();
+        println!("⏱️ Run {}/{}: {:.2}s", run, runs, elapsed);
+        durations.push(elapsed);
+    }
+
+    durations.sort_by(f64::total_cmp);
+    let median = (durations[(durations.len() - 1) / 2] + durations[durations.len() / 2]) / 2.0;
+    let mean = durations.iter().sum::<f64>() / durations.len() as f64;
+    println!(
+        "📊 Clean configure+build over {} runs: min {:.2}s, median {:.2}s, max {:.2}s, mean {:.2}s",
+        runs,
+        durations[0],
+        median,
+        durations[durations.len() - 1],
+        mean
+    );
+
+    Ok(())
+}
+
+fn clean_build_on_failure(
+    build_dir: &str,
+    enabled: bool,
+    error: Box<dyn std::error::Error>,
+) -> Box<dyn std::error::Error> {
+    if enabled {
+        match fs::remove_dir_all(build_dir) {
+            Ok(()) => info!("🧹 Removed {} after the failed build", build_dir),
+            Err(e) => eprintln!("Warning: Failed to remove {}: {}", build_dir, e),
+        }
+    }
+    error
+}
+
+fn pre_configure_env(source_dir: &str, build_dir: &str) -> Result<Vec<(String, String)>> {
+    Ok(vec![
+        (
+            "CMAKEBUILD_SOURCE_DIR".to_string(),
+            env::current_dir()?.join(source_dir).to_string_lossy().into_owned(),
+        ),
+        (
+            "CMAKEBUILD_BUILD_DIR".to_string(),
+            env::current_dir()?.join(build_dir).to_string_lossy().into_owned(),
+        ),
+    ])
+}
+
+fn configure_command(config_params: &[String], fresh: bool, trace: bool) -> Vec<String> {
+    let mut params = config_params.to_vec();
+    if fresh {
+        params.push("--fresh".to_string());
+    }
+    if trace {
+        params.push("--trace-expand".to_string());
+    }
+    params
+}
+
+pub struct ScriptCommand {
+    pub args: Vec<String>,
+    pub envs: Vec<(String, String)>,
+    pub stdin: Option<String>,
+}
+
+impl ScriptCommand {
+    fn new(program: &str, params: Vec<String>) -> Self {
+        let args = [vec![program.to_string()], params].concat();
+        Self { args, envs: Vec::new(), stdin: None }
+    }
+}
+
+pub fn dump_commands(path: &Path, commands: &[ScriptCommand]) -> Result<()> {
+    let batch = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("bat") || ext.eq_ignore_ascii_case("cmd"));
+    let current_dir = env::current_dir()?.to_string_lossy().into_owned();
+
+    let script = if batch {
+        let mut script = format!("@echo off\r\ncd /d {}\r\n", batch_quote(&current_dir));
+        for command in commands {
+            if !command.envs.is_empty() {
+                script.push_str("setlocal\r\n");
+            }
+            for (key, value) in &command.envs {
+                script.push_str(&format!("set {}\r\n", batch_quote(&format!("{}={}", key, value))));
+            }
+            let line: Vec<String> = command.args.iter().map(|arg| batch_quote(arg)).collect();
+            script.push_str(&line.join(" "));
+            if let Some(stdin) = &command.stdin {
+                script.push_str(&format!(" < {}", batch_quote(stdin)));
+            }
+            script.push_str(" || exit /b 1\r\n");
+            if !command.envs.is_empty() {
+                script.push_str("endlocal\r\n");
+            }
+        }
+        script
+    } else {
+        let mut script = format!("#!/bin/sh\nset -e\ncd {}\n", shell_quote(&current_dir));
+        for command in commands {
+            for (key, value) in &command.envs {
+                script.push_str(&format!("{}={} ", key, shell_quote(value)));
+            }
+            let line: Vec<String> = command.args.iter().map(|arg| shell_quote(arg)).collect();
+            script.push_str(&line.join(" "));
+            if let Some(stdin) = &command.stdin {
+                script.push_str(&format!(" < {}", shell_quote(stdin)));
+            }
+            script.push('\n');
+        }
+        script
+    };
+
+    fs::write(path, script).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    if !batch {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    info!("📝 Wrote the build commands to {}", path.display());
+
+    Ok(())
+}
+
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:=,+@%".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+fn batch_quote(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if !arg.is_empty() && !arg.contains([' ', '\t', '&', '|', '<', '>', '^', '"', ';', ',', '=']) {
+        arg
+    } else {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    }
+}
+
+pub fn init_project(name: &str) -> Result<()> {
+    // The name becomes both the CMake target and the program the build runs afterwards
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || "_.+-".contains(c)) {
+        return Err(format!(
+            "'{}' is not a valid CMake target name; rename the directory using only letters, digits and _.+-",
+            name
+        )
+        .into());
+    }
+
+    // Hidden entries such as .git or .vscode are fine, anything else means an existing project
+    let occupied = fs::read_dir(".")?
+        .flatten()
+        .any(|entry| !entry.file_name().as_encoded_bytes().starts_with(b"."));
+    if occupied {
+        return Err("The current directory is not empty, refusing to scaffold a project into it".into());
+    }
+
+    let cmake_lists = Path::new("CMakeLists.txt");
+    let main_cpp = Path::new("src/main.cpp");
+
+    fs::write(
+        cmake_lists,
+        format!(
+            "cmake_minimum_required(VERSION 3.25)\n\
+             \n\
+             # Set the project name\n\
+             project({name})\n\
+             \n\
+             # Add an executable\n\
+             add_executable({name} src/main.cpp)\n"
+        ),
+    )?;
+    fs::create_dir_all("src")?;
+    fs::write(
+        main_cpp,
+        "#include <iostream>\n\
+         \n\
+         int main() {\n    \
+             std::cout << \"Hello World!\" << std::endl;\n\
+         }\n",
+    )?;
+
+    println!("✨ Created {} and {} for project {}", cmake_lists.display(), main_cpp.display(), name);
+    Ok(())
+}
+
+pub fn project_subdir(project: &str) -> Result<String> {
+    let mut parts = Vec::new();
+    for component in Path::new(project).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            // The build directory mirrors this path under build/, so it must not escape it
+            _ => return Err(format!("--project must be a subdirectory of the current directory, got '{}'", project).into()),
+        }
+    }
+    if parts.is_empty() {
+        return Err(format!("--project must name a subdirectory, got '{}'", project).into());
+    }
+
+    Ok(parts.join("/"))
+}
+
+struct SingleFileProject {
+    name: String,
+    dir: PathBuf,
+    keep: bool,
+}
+
+impl SingleFileProject {
+    fn create(file: &str, keep: bool) -> Result<Self> {
+        let source = env::current_dir()?.join(file);
+        if !source.is_file() {
+            return Err(format!("Source file not found: {}", file).into());
+        }
+        let stem = source
+            .file_stem()
+            .ok_or_else(|| format!("Cannot derive a program name from {}", file))?;
+        let name = utf8_name(stem)?.to_string();
+
+        let dir = env::current_dir()?.join("build/single").join(&name).join("project");
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join("CMakeLists.txt"),
+            format!(
+                "cmake_minimum_required(VERSION 3.25)\n\
+                 project({name})\n\
+                 add_executable({name} \"{}\")\n",
+                source.to_string_lossy().replace('\\', "/")
+            ),
+        )?;
+
+        Ok(Self { name, dir, keep })
+    }
+}
+
+impl Drop for SingleFileProject {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+fn print_settings(settings: &[(&str, String)]) {
+    println!("⚙️ Resolved configuration:");
+    for (name, value) in settings {
+        println!("   {:<16} {}", format!("{}:", name), value);
+    }
+}
+
+pub fn check_project_path(path: &Path, strict: bool) -> Result<()> {
+    let path = path.to_string_lossy();
+    let problem = if path.contains(' ') {
+        "contains spaces"
+    } else if !path.is_ascii() {
+        "contains non-ASCII characters"
+    } else {
+        return Ok(());
+    };
+
+    let message = format!(
+        "Project path '{}' {}. MinGW Makefiles and some CMake generators cannot handle such paths \
+         and fail during configure or build; move the project to a plain ASCII path without spaces",
+        path, problem
+    );
+    if strict {
+        return Err(message.into());
+    }
+    eprintln!("Warning: {}", message);
+
+    Ok(())
+}
+
+pub fn ensure_writable(dir: &str) -> Result<()> {
+    let probe = Path::new(dir).join(".write_test");
+    let result = fs::create_dir_all(dir).and_then(|_| fs::write(&probe, b"")).and_then(|_| fs::remove_file(&probe));
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(e) if matches!(e.kind(), ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem) => Err(format!(
+            "No write access to '{}' ({}). This tool writes its build output under build/ in the current directory; \
+             run it from a writable project directory or fix the directory permissions",
+            dir, e
+        )
+        .into()),
+        Err(e) => Err(format!("Failed to prepare directory {}: {}", dir, e).into()),
+    }
+}
+
+pub fn add_tool_to_path(name: &str, root: &Path) -> Result<()> {
+    let bin_dir = root.join("bin");
+    if !bin_dir.is_dir() {
+        return Err(format!("Tool root for {} has no bin directory: {}", name, bin_dir.display()).into());
+    }
+
+    let mut paths = vec![bin_dir.clone()];
+    if let Some(path) = search_path() {
+        paths.extend(env::split_paths(&path));
+    }
+    set_tool_path(Some(env::join_paths(paths)?));
+    info!("🔧 Using {} from {}", name, bin_dir.display());
+
+    Ok(())
+}
+
+pub fn write_activation_scripts(dir: &Path, bin_dirs: &[PathBuf]) -> Result<()> {
+    let join = |separator: &str| {
+        bin_dirs
+            .iter()
+            .map(|dir| format!("{}{}", dir.display(), separator))
+            .collect::<String>()
+    };
+
+    let scripts = [
+        (
+            "activate.sh",
+            format!(
+                "_CMAKEBUILD_OLD_PATH=\"$PATH\"\n\
+                 PATH=\"{}$PATH\"\n\
+                 export PATH\n\
+                 \n\
+                 deactivate() {{\n    \
+                     PATH=\"$_CMAKEBUILD_OLD_PATH\"\n    \
+                     export PATH\n    \
+                     unset _CMAKEBUILD_OLD_PATH\n    \
+                     unset -f deactivate\n\
+                 }}\n",
+                join(":")
+            ),
+        ),
+        (
+            "activate.ps1",
+            format!(
+                "$env:_CMAKEBUILD_OLD_PATH = $env:PATH\n\
+                 $env:PATH = \"{}\" + $env:PATH\n\
+                 \n\
+                 function global:deactivate {{\n    \
+                     $env:PATH = $env:_CMAKEBUILD_OLD_PATH\n    \
+                     Remove-Item Env:_CMAKEBUILD_OLD_PATH\n    \
+                     Remove-Item Function:deactivate\n\
+                 }}\n",
+                join(";")
+            ),
+        ),
+        (
+            "activate.bat",
+            format!(
+                "@echo off\r\n\
+                 set \"_CMAKEBUILD_OLD_PATH=%PATH%\"\r\n\
+                 set \"PATH={}%PATH%\"\r\n",
+                join(";")
+            ),
+        ),
+        (
+            "deactivate.bat",
+            "@echo off\r\n\
+             set \"PATH=%_CMAKEBUILD_OLD_PATH%\"\r\n\
+             set \"_CMAKEBUILD_OLD_PATH=\"\r\n"
+                .to_string(),
+        ),
+    ];
+
+    fs::create_dir_all(dir)?;
+    for (name, content) in scripts {
+        let path = dir.join(name);
+        fs::write(&path, content)?;
+        println!("✨ Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+pub fn resolve_build_type(build_type: &str, aliases: &[String]) -> Result<String> {
+    for alias in aliases {
+        let (name, canonical) = alias
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid build type alias '{}', expected ALIAS=TYPE", alias))?;
+        if name == build_type {
+            return Ok(canonical.to_string());
+        }
+    }
+
+    Ok(match build_type {
+        "dev" => "Debug",
+        "dist" => "Release",
+        other => other,
+    }
+    .to_string())
+}
+
+pub fn host_architecture() -> Result<&'static str> {
+    match env::consts::ARCH {
+        "x86_64" => Ok("x64"),
+        "x86" => Ok("x86"),
+        other => Err(format!("Unsupported host architecture: {} (pass --architecture x64 or x86)", other).into()),
+    }
+}
+
+pub fn resolve_jobs(jobs: &str) -> Result<u32> {
+    if jobs != "auto" {
+        return jobs
+            .parse()
+            .ok()
+            .filter(|&jobs| jobs > 0)
+            .ok_or_else(|| format!("Invalid --jobs '{}', expected a positive number or auto", jobs).into());
+    }
+
+    let cores = thread::available_parallelism().map_or(1, |cores| cores.get() as u32);
+    // Heavy template instantiation can take about a gigabyte per compiler process
+    let jobs = match available_memory() {
+        Some(bytes) => cores.min((bytes >> 30).max(1) as u32),
+        None => cores,
+    };
+    info!("🧮 Using {} parallel jobs ({} cores)", jobs, cores);
+
+    Ok(jobs)
+}
+
+fn available_memory() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let kib: u64 = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+pub fn resolve_toolchain(arch: &str) -> Result<(&'static str, &'static str, &'static str, &'static str)> {
+    match std::env::consts::OS {
+        "windows" => Ok(configure_windows(arch)),
+        "linux" => Ok(configure_linux(arch)),
+        os => Err(format!("Unsupported OS: {}", os).into()),
+    }
+}
+
+pub fn triple_compilers(triple: &str) -> (String, String) {
+    let suffix = if std::env::consts::OS == "windows" { ".exe" } else { "" };
+    (format!("{}-gcc{}", triple, suffix), format!("{}-g++{}", triple, suffix))
+}
+
+pub fn toolchain_compilers(toolchain: &str, arch: &str, triple: Option<&str>) -> Result<(String, String, String)> {
+    let suffix = if std::env::consts::OS == "windows" { ".exe" } else { "" };
+
+    match (toolchain, triple) {
+        ("gcc", Some(triple)) => {
+            let (c_compiler, cxx_compiler) = triple_compilers(triple);
+            Ok((String::new(), c_compiler, cxx_compiler))
+        }
+        ("gcc", None) => {
+            let (_, flags, c_compiler, cxx_compiler) = resolve_toolchain(arch)?;
+            Ok((flags.to_string(), c_compiler.to_string(), cxx_compiler.to_string()))
+        }
+        ("clang", triple) => {
+            let flags = match triple {
+                Some(triple) => format!("--target={}", triple),
+                None => resolve_toolchain(arch)?.1.to_string(),
+            };
+            Ok((flags, format!("clang{}", suffix), format!("clang++{}", suffix)))
+        }
+        (other, _) => Err(format!("Unsupported toolchain: {} (expected gcc or clang)", other).into()),
+    }
+}
+
+pub fn select_toolchain(
+    toolchain: &str,
+    arch: &str,
+    triple: Option<&str>,
+    strict: bool,
+) -> Result<(&'static str, String, String, String)> {
+    let (requested, fallback) = match toolchain {
+        "gcc" => ("gcc", "clang"),
+        "clang" => ("clang", "gcc"),
+        other => return Err(format!("Unsupported toolchain: {} (expected gcc or clang)", other).into()),
+    };
+
+    let (flags, c_compiler, cxx_compiler) = toolchain_compilers(requested, arch, triple)?;
+    if compilers_available(&c_compiler, &cxx_compiler) {
+        return Ok((requested, flags, c_compiler, cxx_compiler));
+    }
+
+    let missing = format!("{} / {} not found in PATH", c_compiler, cxx_compiler);
+    if strict {
+        return Err(format!("Toolchain '{}' is not available: {}", requested, missing).into());
+    }
+
+    let (fallback_flags, fallback_c, fallback_cxx) = toolchain_compilers(fallback, arch, triple)?;
+    if compilers_available(&fallback_c, &fallback_cxx) {
+        eprintln!("Warning: Toolchain '{}' is not available ({}), falling back to '{}'", requested, missing, fallback);
+        return Ok((fallback, fallback_flags, fallback_c, fallback_cxx));
+    }
+
+    Err(format!("Neither '{}' nor '{}' toolchain is available: {}", requested, fallback, missing).into())
+}
+
+pub fn compiler_target(compiler: &str, flags: &str) -> Option<String> {
+    let output = tool_command(compiler)
+        .args(flags.split_whitespace())
+        .arg("-dumpmachine")
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn check_compiler_target(compiler: &str, flags: &str, arch: &str, triple: Option<&str>) {
+    let Some(machine) = compiler_target(compiler, flags) else {
+        eprintln!("Warning: Could not determine the target of {}", compiler);
+        return;
+    };
+    info!("🎯 Compiler target: {}", machine);
+
+    let matches = match triple {
+        Some(triple) => machine == triple,
+        None => match arch {
+            "x64" => machine.starts_with("x86_64"),
+            // multilib GCC reports its 64-bit default target even with -m32
+            "x86" => is_x86_32(&machine) || (machine.starts_with("x86_64") && flags.contains("-m32")),
+            _ => true,
+        },
+    };
+    if !matches {
+        eprintln!(
+            "Warning: {} targets {}, which does not match the requested {}",
+            compiler,
+            machine,
+            triple.unwrap_or(arch)
+        );
+    }
+}
+
+pub fn compiler_version(compiler: &str) -> Option<String> {
+    let output = tool_command(compiler).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .next()?
+        .split_whitespace()
+        .find(|token| token.contains('.') && token.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        .map(String::from)
+}
+
+fn check_compiler_version(compiler: &str, required: &str) -> Result<()> {
+    let actual = compiler_version(compiler)
+        .ok_or_else(|| format!("Could not determine the version of {}", compiler))?;
+
+    if actual == required || actual.starts_with(&format!("{}.", required)) {
+        info!("✅ {} version {} matches the required {}", compiler, actual, required);
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is version {}, but version {} is required (use --no-version-check to skip this check)",
+            compiler, actual, required
+        )
+        .into())
+    }
+}
+
+fn is_x86_32(machine: &str) -> bool {
+    ["i386", "i486", "i586", "i686"].iter().any(|prefix| machine.starts_with(prefix))
+}
+
+fn compilers_available(c_compiler: &str, cxx_compiler: &str) -> bool {
+    find_in_path(c_compiler).is_some() && find_in_path(cxx_compiler).is_some()
+}
+
+fn configure_windows(arch: &str) -> (&'static str, &'static str, &'static str, &'static str) {
+    match arch {
+        "x64" => ("MinGW Makefiles", "-m64", "x86_64-w64-mingw32-gcc.exe", "x86_64-w64-mingw32-g++.exe"),
+        "x86" => ("MinGW Makefiles", "-m32", "i686-w64-mingw32-gcc.exe", "i686-w64-mingw32-g++.exe"),
+        _ => ("", "", "", ""),
+    }
+}
+
+pub fn configure_linux(arch: &str) -> (&'static str, &'static str, &'static str, &'static str) {
+    match arch {
+        "x64" => ("Unix Makefiles", "-m64", "gcc", "g++"),
+        "x86" => ("Unix Makefiles", "-m32", "gcc", "g++"),
+        _ => ("", "", "", ""),
+    }
+}
+
+pub fn search_paths(dirs: &[String]) -> Result<String> {
+    let mut paths = Vec::new();
+    for dir in dirs {
+        let path = env::current_dir()?.join(dir);
+        if !path.is_dir() {
+            return Err(format!("Search directory not found: {}", dir).into());
+        }
+        paths.push(path.to_string_lossy().into_owned());
+    }
+    Ok(paths.join(";"))
+}
+
+pub fn read_cmake_args_file(path: &str) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read CMake args file {}: {}", path, e))?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+fn open_ide(ide: &str) -> Result<()> {
+    let launcher = match ide {
+        "vscode" => "code",
+        "clion" => "clion",
+        "qtcreator" => "qtcreator",
+        _ => {
+            eprintln!("Warning: Don't know how to open IDE '{}', skipping", ide);
+            return Ok(());
+        }
+    };
+
+    match find_in_path(launcher) {
+        Some(path) => {
+            run_command(&path, &["."])?;
+        }
+        None => eprintln!("Warning: IDE launcher '{}' not found in PATH, skipping", launcher),
+    }
+
+    Ok(())
+}
+
+fn open_in_file_manager(dir: &str) {
+    let opener = match std::env::consts::OS {
+        "windows" => "explorer",
+        "macos" => "open",
+        _ => "xdg-open",
+    };
+
+    // explorer reports a non-zero exit status even when it opens the folder
+    if let Err(e) = run_command(opener, &[dir])
+        && std::env::consts::OS != "windows"
+    {
+        eprintln!("Warning: Failed to open {}: {}", dir, e);
+    }
+}
+
+fn set_tool_path(path: Option<OsString>) {
+    if let Ok(mut tool_path) = TOOL_PATH.lock() {
+        *tool_path = path;
+    }
+}
+
+fn search_path() -> Option<OsString> {
+    let tool_path = TOOL_PATH.lock().ok().and_then(|path| path.clone());
+    tool_path.or_else(|| env::var_os("PATH"))
+}
+
+fn tool_command(program: &str) -> Command {
+    let mut command = Command::new(program);
+    if let Some(path) = TOOL_PATH.lock().ok().and_then(|path| path.clone()) {
+        command.env("PATH", path);
+    }
+    command
+}
+
+pub fn find_in_path(program: &str) -> Option<String> {
+    let extensions: &[&str] = match std::env::consts::OS {
+        "windows" if Path::new(program).extension().is_none() => &[".exe", ".cmd", ".bat"],
+        _ => &[""],
+    };
+
+    env::split_paths(&search_path()?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", program, ext)))
+            .find(|path| path.is_file())
+            .map(|path| path.to_string_lossy().into_owned())
+    })
+}
+
+fn overrides_compiler_flags(arg: &str) -> bool {
+    ["-DCMAKE_C_FLAGS=", "-DCMAKE_C_FLAGS:", "-DCMAKE_CXX_FLAGS=", "-DCMAKE_CXX_FLAGS:"]
+        .iter()
+        .any(|prefix| arg.starts_with(prefix))
+}
+
+pub fn cmake_version() -> Option<(u32, u32)> {
+    let output = tool_command("cmake").arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines().next()?.strip_prefix("cmake version ")?;
+    let mut parts = version.trim().split('.');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+pub fn cached_generator(build_dir: &str) -> Option<String> {
+    let cache = fs::read_to_string(Path::new(build_dir).join("CMakeCache.txt")).ok()?;
+    cache
+        .lines()
+        .find_map(|line| line.strip_prefix("CMAKE_GENERATOR:INTERNAL="))
+        .map(String::from)
+}
+
+fn clear_cache_on_generator_change(build_dir: &str, generator: &str) -> Result<()> {
+    let Some(previous) = cached_generator(build_dir) else {
+        return Ok(());
+    };
+    if previous == generator {
+        return Ok(());
+    }
+
+    info!("🧹 Generator changed from '{}' to '{}', clearing CMake cache in {}", previous, generator, build_dir);
+    let build_dir = Path::new(build_dir);
+    fs::remove_file(build_dir.join("CMakeCache.txt"))?;
+    let cmake_files = build_dir.join("CMakeFiles");
+    if cmake_files.exists() {
+        fs::remove_dir_all(cmake_files)?;
+    }
+
+    Ok(())
+}
+
+pub fn source_snapshot(root: &Path, config_params: &[String], excluded: &[PathBuf]) -> io::Result<String> {
+    // Build outputs inside the source tree would change on every build and never match
+    let absolute_root = env::current_dir()?.join(root);
+    let excluded: Vec<&Path> = excluded
+        .iter()
+        .filter_map(|path| path.strip_prefix(&absolute_root).ok())
+        .collect();
+
+    let mut entries = Vec::new();
+    collect_source_entries(root, root, &excluded, &mut entries)?;
+    entries.sort();
+
+    let mut snapshot = config_params.join("\n");
+    snapshot.push_str("\n\n");
+    for entry in entries {
+        snapshot.push_str(&entry);
+        snapshot.push('\n');
+    }
+    Ok(snapshot)
+}
+
+fn collect_source_entries(root: &Path, dir: &Path, excluded: &[&Path], entries: &mut Vec<String>) -> io::Result<()> {
+    let context = |path: &Path, e: io::Error| io::Error::new(e.kind(), format!("{}: {}", path.display(), e));
+    for entry in fs::read_dir(dir).map_err(|e| context(dir, e))? {
+        let entry = entry.map_err(|e| context(dir, e))?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || (dir == root && name == "build") {
+            continue;
+        }
+
+        let path = entry.path();
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let metadata = entry.metadata().map_err(|e| context(&path, e))?;
+        if metadata.is_dir() {
+            if !excluded.contains(&relative) {
+                collect_source_entries(root, &path, excluded, entries)?;
+            }
+        } else {
+            let modified = metadata
+                .modified()
+                .map_err(|e| context(&path, e))?
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            entries.push(format!("{}\t{}\t{}", relative.display(), metadata.len(), modified.as_nanos()));
+        }
+    }
+    Ok(())
+}
+
+pub fn relink(build_dir: &str, bin_dir: &str, program_name: &str, generator: &str) -> Result<()> {
+    // Removing the executable leaves the objects up to date, so only the link rule runs
+    let exe = Path::new(bin_dir).join(format!("{}{}", program_name, env::consts::EXE_SUFFIX));
+    if exe.exists() {
+        fs::remove_file(&exe)?;
+    }
+
+    // Makefile generators provide <target>/fast, which skips the dependency scan
+    let target = if generator.ends_with("Makefiles") {
+        format!("{}/fast", program_name)
+    } else {
+        program_name.to_string()
+    };
+    run_command("cmake", &["--build", build_dir, "--target", &target])?;
+
+    Ok(())
+}
+
+pub fn package(build_dir: &str, generator: &str) -> Result<()> {
+    let config = Path::new(build_dir).join("CPackConfig.cmake");
+    if !config.is_file() {
+        return Err(format!("{} not found, does the project include(CPack)?", config.display()).into());
+    }
+
+    let before = file_mtimes(build_dir)?;
+    run_command("cpack", &cpack_params(build_dir, generator))?;
+
+    for (path, modified) in file_mtimes(build_dir)? {
+        if before.get(&path) != Some(&modified) {
+            info!("📦 Package: {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn cpack_params(build_dir: &str, generator: &str) -> Vec<String> {
+    let config = Path::new(build_dir).join("CPackConfig.cmake");
+    let mut params = vec![
+        "--config".to_string(),
+        config.to_string_lossy().into_owned(),
+        "-B".to_string(),
+        build_dir.to_string(),
+    ];
+    if !generator.is_empty() {
+        params.extend(["-G".to_string(), generator.to_string()]);
+    }
+    params
+}
+
+fn file_mtimes(dir: &str) -> io::Result<HashMap<PathBuf, std::time::SystemTime>> {
+    let mut mtimes = HashMap::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            mtimes.insert(entry.path(), metadata.modified()?);
+        }
+    }
+    Ok(mtimes)
+}
+
+pub fn strip_command(c_compiler: &str) -> String {
+    match c_compiler.rfind("gcc") {
+        Some(index) => format!("{}strip{}", &c_compiler[..index], &c_compiler[index + 3..]),
+        None => "strip".to_string(),
+    }
+}
+
+pub fn strip_executables(bin_dir: &str, strip: &str) -> Result<()> {
+    let Ok(entries) = fs::read_dir(bin_dir) else {
+        return Ok(());
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() && is_executable(&entry.path(), &metadata) {
+            run_command(strip, &[entry.path().to_string_lossy()])?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(_path: &Path, metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path, _metadata: &fs::Metadata) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+}
+
+fn is_library(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_ascii_lowercase();
+    [".a", ".lib", ".dll", ".dylib", ".so"].iter().any(|ext| name.ends_with(ext)) || name.contains(".so.")
+}
+
+pub fn write_checksums(bin_dir: &str, build_dir: &str) -> Result<()> {
+    let Ok(entries) = fs::read_dir(bin_dir) else {
+        return Ok(());
+    };
+
+    let mut sums = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() && is_executable(&entry.path(), &metadata) {
+            let mut hasher = Sha256::new();
+            io::copy(&mut fs::File::open(entry.path())?, &mut hasher)?;
+            sums.push((entry.file_name().to_string_lossy().into_owned(), format!("{:x}", hasher.finalize())));
+        }
+    }
+    sums.sort();
+
+    let sums_path = Path::new(build_dir).join("SHA256SUMS");
+    let content: String = sums
+        .iter()
+        .map(|(name, hash)| format!("{}  {}\n", hash, name))
+        .collect();
+    fs::write(&sums_path, content)?;
+    info!("🔒 Wrote {}", sums_path.display());
+
+    Ok(())
+}
+
+pub fn report_artifact_sizes(bin_dir: &str, build_dir: &str) -> Result<()> {
+    let Ok(entries) = fs::read_dir(bin_dir) else {
+        return Ok(());
+    };
+
+    let mut artifacts = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        // With --no-default-flags this is the build dir itself, next to CMake's and our own files
+        if metadata.is_file() && (is_executable(&entry.path(), &metadata) || is_library(&entry.path())) {
+            artifacts.push((entry.file_name().to_string_lossy().into_owned(), metadata.len()));
+        }
+    }
+    artifacts.sort();
+
+    let record_path = format!("{}/artifact_sizes.txt", build_dir);
+    let previous: HashMap<String, u64> = fs::read_to_string(&record_path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (name, size) = line.rsplit_once('\t')?;
+            Some((name.to_string(), size.parse().ok()?))
+        })
+        .collect();
+
+    info!("📦 Build artifacts:");
+    for (name, size) in &artifacts {
+        match previous.get(name) {
+            Some(&old) if old != *size => {
+                let sign = if *size > old { '+' } else { '-' };
+                info!("   {} {} ({}{})", name, HumanBytes(*size), sign, HumanBytes(size.abs_diff(old)));
+            }
+            _ => info!("   {} {}", name, HumanBytes(*size)),
+        }
+    }
+
+    let record: String = artifacts
+        .iter()
+        .map(|(name, size)| format!("{}\t{}\n", name, size))
+        .collect();
+    fs::write(&record_path, record)?;
+
+    Ok(())
+}
+
+pub fn read_env_file(path: &str) -> Result<Vec<(String, String)>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read env file {}: {}", path, e))?;
+
+    let mut vars = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("{}:{}: expected KEY=VALUE", path, index + 1))?;
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("{}:{}: invalid variable name '{}'", path, index + 1, key).into());
+        }
+
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value
+                .strip_prefix(quote)
+                .and_then(|rest| rest.strip_suffix(quote))
+                .ok_or_else(|| format!("{}:{}: unterminated quoted value", path, index + 1))?,
+            _ => value,
+        };
+        vars.push((key.to_string(), value.to_string()));
+    }
+
+    Ok(vars)
+}
+
+pub fn get_current_dir_name() -> Result<&'static str> {
+    utf8_name(CURRENT_DIR_NAME.as_deref().ok_or(ROOT_DIR_ERROR)?)
+}
+
+fn utf8_name(name: &OsStr) -> Result<&str> {
+    name.to_str().ok_or_else(|| {
+        format!(
+            "'{}' is not valid UTF-8, so no program name can be derived from it; pass --program-name",
+            name.to_string_lossy()
+        )
+        .into()
+    })
+}
+
+fn utf8_path(path: &Path) -> Result<&str> {
+    path.to_str().ok_or_else(|| {
+        format!(
+            "'{}' is not valid UTF-8, which CMake cannot be given; build from a directory with a UTF-8 path",
+            path.to_string_lossy()
+        )
+        .into()
+    })
+}
+
+pub fn run_command<S: AsRef<str>>(command: &str, args: &[S]) -> Result<ExitStatus> {
+    run_command_with_timeout(command, args, None)
+}
+
+pub fn run_command_with_timeout<S: AsRef<str>>(command: &str, args: &[S], timeout: Option<Duration>) -> Result<ExitStatus> {
+    let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+    info!("🚀 Executing: {} {}", command, args.join(" "));
+
+    check_status(execute(tool_command(command).args(&args), command, timeout, captured_output(), None)?)
+}
+
+pub fn run_program<S: AsRef<str>>(
+    program: &str,
+    args: &[S],
+    envs: &[(String, String)],
+    timeout: Option<Duration>,
+    tee_output: Option<&Path>,
+    stdin: Option<&Path>,
+    retry_delay: Option<Duration>,
+) -> Result<ExitStatus> {
+    let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+    info!("🚀 Executing: {} {}", program, args.join(" "));
+
+    let output = match tee_output {
+        Some(path) => Output::Tee(
+            fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?,
+        ),
+        None => Output::Inherit,
+    };
+
+    let mut command = tool_command(program);
+    command.args(&args).envs(envs.iter().cloned());
+    if let Some(path) = stdin {
+        command.stdin(fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?);
+    }
+    let status = execute(&mut command, program, timeout, output, retry_delay)?;
+    if status.success() {
+        Ok(status)
+    } else {
+        Err(format!("Program exited with status: {}", status).into())
+    }
+}
+
+fn captured_output() -> Output {
+    if summary_only() { Output::Capture } else { Output::Inherit }
+}
+
+enum Output {
+    Inherit,
+    Capture,
+    Tee(fs::File),
+}
+
+fn execute(
+    command: &mut Command,
+    name: &str,
+    timeout: Option<Duration>,
+    output: Output,
+    retry_delay: Option<Duration>,
+) -> Result<ExitStatus> {
+    if !matches!(output, Output::Inherit) {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    if timeout.is_some() {
+        own_process_group(command);
+    }
+
+    let mut child = match (command.spawn(), retry_delay) {
+        (Err(e), Some(delay)) if is_sharing_violation(&e) => {
+            info!("⏳ {} is still locked, retrying in {}ms", name, delay.as_millis());
+            thread::sleep(delay);
+            command.spawn()
+        }
+        (spawned, _) => spawned,
+    }
+    .map_err(|e| command_error(name, e))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (status, captured) = thread::scope(|scope| {
+        let stdout = stdout.map(|stream| scope.spawn(|| pump_stream(stream, io::stdout(), &output)));
+        let stderr = stderr.map(|stream| scope.spawn(|| pump_stream(stream, io::stderr(), &output)));
+        let status = wait_with_timeout(&mut child, name, timeout);
+        let captured: Vec<u8> = [stdout, stderr]
+            .into_iter()
+            .flatten()
+            .flat_map(|reader| reader.join().unwrap_or_default())
+            .collect();
+        (status, captured)
+    });
+
+    if matches!(output, Output::Capture) && !status.as_ref().is_ok_and(ExitStatus::success) {
+        let _ = io::stderr().write_all(&captured);
+    }
+
+    status
+}
+
+fn pump_stream<R: io::Read, W: Write>(mut stream: R, mut console: W, output: &Output) -> Vec<u8> {
+    let mut captured = Vec::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let read = match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => &buffer[..read],
+        };
+        match output {
+            Output::Inherit => {}
+            Output::Capture => captured.extend_from_slice(read),
+            Output::Tee(file) => {
+                let _ = console.write_all(read).and_then(|_| console.flush());
+                let _ = (&*file).write_all(read);
+            }
+        }
+    }
+    captured
+}
+
+pub fn run_command_with_progress<S: AsRef<str>>(
+    command: &str,
+    args: &[S],
+    progress: &dyn Progress,
+    timeout: Option<Duration>,
+) -> Result<ExitStatus> {
+    let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+    info!("🚀 Executing: {} {}", command, args.join(" "));
+
+    let mut command_line = tool_command(command);
+    command_line.args(&args).stdout(Stdio::piped());
+    // Keep the colours the build would print if its output were not piped through us
+    if io::stdout().is_terminal() {
+        command_line.env("CLICOLOR_FORCE", "1");
+    }
+    if timeout.is_some() {
+        own_process_group(&mut command_line);
+    }
+    let mut child = command_line.spawn().map_err(|e| command_error(command, e))?;
+
+    let stdout = child.stdout.take();
+    let status = thread::scope(|scope| {
+        scope.spawn(|| {
+            let Some(stdout) = stdout else { return };
+            for line in BufReader::new(stdout).lines().map_while(io::Result::ok) {
+                println!("{}", line);
+                if let Some(percent) = parse_build_percent(&line) {
+                    progress.percent(percent);
+                }
+            }
+        });
+        wait_with_timeout(&mut child, command, timeout)
+    });
+    progress.finish();
+
+    check_status(status?)
+}
+
+fn wait_with_timeout(child: &mut Child, command: &str, timeout: Option<Duration>) -> Result<ExitStatus> {
+    let Some(timeout) = timeout else {
+        return Ok(child.wait()?);
+    };
+
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if start.elapsed() >= timeout {
+            kill_process_tree(child);
+            return Err(format!("Command timed out after {}s: {}", timeout.as_secs(), command).into());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+// A timed-out build is killed as a whole group, so compilers spawned after the
+// timeout fires cannot survive and keep our output pipes open. Only commands with
+// a timeout get their own group, since that takes them out of the terminal's
+// foreground group and Ctrl+C would no longer reach them.
+#[cfg(unix)]
+fn own_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn own_process_group(_command: &mut Command) {}
+
+fn kill_process_tree(child: &mut Child) {
+    if std::env::consts::OS == "windows" {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    } else {
+        let _ = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", child.id())])
+            .stderr(Stdio::null())
+            .status();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+pub fn run_shell_command(script: &str, vars: &[(String, String)]) -> Result<ExitStatus> {
+    info!("🚀 Executing: {}", script);
+
+    let (shell, flag) = system_shell();
+    let mut command = tool_command(shell);
+    command.args([flag, script]).envs(vars.iter().cloned());
+    check_status(execute(&mut command, shell, None, captured_output(), None)?)
+}
+
+fn system_shell() -> (&'static str, &'static str) {
+    match std::env::consts::OS {
+        "windows" => ("cmd", "/C"),
+        _ => ("sh", "-c"),
+    }
+}
+
+pub fn parse_build_percent(line: &str) -> Option<u32> {
+    let progress = line.trim_start().strip_prefix('[')?.split(']').next()?.trim();
+
+    if let Some(percent) = progress.strip_suffix('%') {
+        return percent.trim().parse().ok();
+    }
+
+    let (done, total) = progress.split_once('/')?;
+    let done: u32 = done.trim().parse().ok()?;
+    let total: u32 = total.trim().parse().ok()?;
+    (total > 0).then(|| done * 100 / total)
+}
+
+#[cfg(windows)]
+fn enable_virtual_terminal() -> bool {
+    type Handle = *mut std::ffi::c_void;
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetStdHandle(std_handle: u32) -> Handle;
+        fn GetConsoleMode(console: Handle, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: Handle, mode: u32) -> i32;
+    }
+
+    // SAFETY: the handle comes straight from GetStdHandle and mode outlives the calls.
+    unsafe {
+        let console = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode = 0;
+        GetConsoleMode(console, &mut mode) != 0
+            && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+    }
+}
+
+#[cfg(not(windows))]
+fn enable_virtual_terminal() -> bool {
+    true
+}
+
+fn set_title_progress(title: &str, percent: u32) {
+    print!("\x1b]0;{}\x07\x1b]9;4;1;{}\x07", title, percent.min(100));
+    let _ = io::stdout().flush();
+}
+
+fn clear_title_progress() {
+    print!("\x1b]0;\x07\x1b]9;4;0;0\x07");
+    let _ = io::stdout().flush();
+}
+
+fn command_error(command: &str, e: io::Error) -> String {
+    if e.kind() == ErrorKind::NotFound {
+        format!("Command not found: {}", command)
+    } else {
+        format!("Command failed: {}", e)
+    }
+}
+
+fn is_sharing_violation(e: &io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION: the linker has not released the executable yet
+    std::env::consts::OS == "windows" && e.raw_os_error() == Some(32)
+}
+
+fn check_status(status: ExitStatus) -> Result<ExitStatus> {
+    if status.success() {
+        Ok(status)
+    } else {
+        Err(format!("Command execution failed with status: {}", status).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_subdirs_stay_under_the_build_dir() {
+        assert_eq!(project_subdir("./libs/foo/").unwrap(), "libs/foo");
+        assert!(project_subdir("../libfoo").is_err());
+        assert!(project_subdir("libs/../../libfoo").is_err());
+        assert!(project_subdir("/opt/libfoo").is_err());
+        assert!(project_subdir(".").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_directory_names_are_rejected() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name_error = utf8_name(OsStr::from_bytes(b"bad\xff")).unwrap_err().to_string();
+        let path_error = utf8_path(Path::new(OsStr::from_bytes(b"/tmp/bad\xff/build"))).unwrap_err().to_string();
+
+        assert!(name_error.contains("pass --program-name"), "{}", name_error);
+        assert!(path_error.contains("not valid UTF-8"), "{}", path_error);
+    }
+}